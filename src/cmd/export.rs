@@ -7,29 +7,45 @@
 //! This interface also allows chaining into another instance of Limber, to
 //! enable piping from one cluster/index to another in a streaming fashion.
 use clap::{value_t, ArgMatches};
-use elastic::client::requests::{ScrollRequest, SearchRequest};
-use elastic::client::AsyncClientBuilder;
-use elastic::prelude::*;
+use elastic::client::{AsyncClient, AsyncClientBuilder};
+use elastic::endpoints::{Endpoint, ScrollRequest, SearchRequest};
+use elastic::http::header::{HeaderValue, AUTHORIZATION};
+use elastic::http::receiver::AsyncResponseBuilder;
+use elastic::http::{Method, UrlPath};
 use failure::{format_err, Error};
 use futures::future::{self, Either, Loop};
 use futures::prelude::*;
+use reqwest::r#async::Client as AsyncHttpClient;
 use serde_json::{json, Value};
 use url::Url;
 
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
+use super::format::Format;
+use super::retry::{read, with_retry, with_retry_throttle};
+
+/// Credentials parsed out of the source URL.
+///
+/// This carries only the inline `user:pass@` userinfo stripped from the
+/// source; explicit `--username`/`--password` flags are resolved later in
+/// `build_client` and take precedence over anything embedded in the URL.
+pub(crate) type Credentials = Option<(String, String)>;
+
 /// Constructs a `Future` to execute the `export` command.
 ///
 /// This future should be spawned on a Runtime to carry out the exporting
 /// process. The returned future will be a combination of several futures
 /// to represent the concurrency flags provided via the CLI arguments.
-pub fn run(args: &ArgMatches) -> Box<Future<Item = (), Error = Error>> {
+pub fn run(args: &ArgMatches) -> Box<dyn Future<Item = (), Error = Error>> {
     // fetch the number of workers to use to export, default to CPU counts
     let workers = value_t!(args, "workers", usize).unwrap_or_else(|_| num_cpus::get());
 
+    // fetch the retry budget applied to each scroll/search request
+    let max_retries = value_t!(args, "max-retries", usize).unwrap_or(5);
+
     // parse arguments into a host/index pairing for later
-    let (host, index) = match parse_cluster_info(&args) {
+    let (hosts, index, creds) = match parse_cluster_info(args) {
         Ok(info) => info,
         Err(e) => {
             let err = future::err(e);
@@ -38,11 +54,19 @@ pub fn run(args: &ArgMatches) -> Box<Future<Item = (), Error = Error>> {
     };
 
     // construct a single client instance to be used across all tasks
-    let client = match AsyncClientBuilder::new().static_node(host).build() {
+    let client = match build_client(args, hosts, creds) {
         Ok(client) => Arc::new(client),
         Err(e) => {
-            let fmt = format_err!("{}", e.to_string());
-            let err = future::err(fmt);
+            let err = future::err(e);
+            return Box::new(err);
+        }
+    };
+
+    // resolve the output format once so all workers share one writer
+    let format = match Format::from_args(args) {
+        Ok(format) => format,
+        Err(e) => {
+            let err = future::err(e);
             return Box::new(err);
         }
     };
@@ -50,6 +74,11 @@ pub fn run(args: &ArgMatches) -> Box<Future<Item = (), Error = Error>> {
     // create counter to track documents added
     let counter = Arc::new(AtomicUsize::new(0));
 
+    // the `--pit` path swaps scroll for a Point-in-Time + search_after loop
+    if args.is_present("pit") {
+        return run_pit(args, client, index, workers, counter, format, max_retries);
+    }
+
     // create vec to store worker task futures
     let mut tasks = Vec::with_capacity(workers);
 
@@ -59,83 +88,103 @@ pub fn run(args: &ArgMatches) -> Box<Future<Item = (), Error = Error>> {
         let index = index.clone();
         let client = client.clone();
         let counter = counter.clone();
+        let format = format.clone();
 
         // create our initial search request to trigger scrolling
-        let request = match construct_query(&args, idx, workers) {
-            Ok(query) => SearchRequest::for_index(index, query),
+        let query = match construct_query(args, idx, workers) {
+            Ok(query) => query,
             Err(e) => {
                 let err = future::err(e);
                 return Box::new(err);
             }
         };
 
-        let execute = client
-            .request(request)
-            .params_fluent(|p| p.url_param("scroll", "1m"))
-            .send()
-            .and_then(AsyncResponseBuilder::into_response)
-            .and_then(|value: Value| {
-                future::loop_fn((counter, client, value), |(counter, client, mut value)| {
-                    // fetch the hits back
-                    let hits = value
-                        .pointer_mut("/hits/hits")
-                        .expect("unable to locate hits")
-                        .as_array_mut()
-                        .expect("hits are of wrong type");
-
-                    // empty hits means we're done
-                    if hits.is_empty() {
-                        let ctx = (counter, client, value);
-                        let brk = Loop::Break(ctx);
-                        let okr = future::ok(brk);
-                        return Either::B(okr);
-                    }
-
-                    // store hit length
-                    let len = hits.len();
-
-                    // iterate docs
-                    for hit in hits {
-                        // grab a mutable reference to the document
-                        let container = hit.as_object_mut().unwrap();
-
-                        // drop some query based fields
-                        container.remove("sort");
-                        container.remove("_score");
-
-                        // drop it to stdout
-                        println!("{}", hit);
-                    }
-
-                    // increment the counter and print the state to stderr
-                    let cnt = counter.fetch_add(len, Ordering::Relaxed);
-                    eprintln!("Fetched batch of {}, have now processed {}", len, cnt + len);
-
-                    // fetch the new scroll_id
-                    let scroll_id = value
-                        .get("_scroll_id")
-                        .expect("unable to locate scroll_id")
-                        .as_str()
-                        .expect("scroll_id is of wrong type")
-                        .to_owned();
-
-                    // construct the request for the next batch
-                    let request = ScrollRequest::for_scroll_id(
-                        scroll_id,
-                        json!({
-                            "scroll": "1m"
-                        }),
-                    );
-
-                    // loop on the next batch
-                    Either::A(
-                        client
-                            .request(request)
-                            .send()
-                            .and_then(AsyncResponseBuilder::into_response)
-                            .and_then(|value: Value| Ok(Loop::Continue((counter, client, value)))),
-                    )
-                })
+        // issue the opening search, retrying transient failures
+        let opening = with_retry(max_retries, {
+            let client = client.clone();
+            move || {
+                let request = SearchRequest::for_index(index.clone(), query.clone());
+                read(client
+                    .request(request)
+                    .params_fluent(|p| p.url_param("scroll", "1m"))
+                    .send())
+            }
+        });
+
+        let execute = opening
+            .and_then(move |value: Value| {
+                future::loop_fn(
+                    (counter, client, format, value),
+                    move |(counter, client, format, mut value)| {
+                        // fetch the hits back
+                        let hits = value
+                            .pointer_mut("/hits/hits")
+                            .expect("unable to locate hits")
+                            .as_array_mut()
+                            .expect("hits are of wrong type");
+
+                        // empty hits means we're done
+                        if hits.is_empty() {
+                            let ctx = (counter, client, format, value);
+                            let brk = Loop::Break(ctx);
+                            let okr = future::ok(brk);
+                            return Either::B(okr);
+                        }
+
+                        // store hit length
+                        let len = hits.len();
+
+                        // iterate docs
+                        for hit in hits {
+                            // grab a mutable reference to the document
+                            let container = hit.as_object_mut().unwrap();
+
+                            // drop some query based fields
+                            container.remove("sort");
+                            container.remove("_score");
+
+                            // serialize via the shared output format
+                            if let Err(e) = format.render(hit) {
+                                let err = future::err(e);
+                                return Either::B(err);
+                            }
+                        }
+
+                        // increment the counter and print the state to stderr
+                        let cnt = counter.fetch_add(len, Ordering::Relaxed);
+                        eprintln!("Fetched batch of {}, have now processed {}", len, cnt + len);
+
+                        // fetch the new scroll_id
+                        let scroll_id = value
+                            .get("_scroll_id")
+                            .expect("unable to locate scroll_id")
+                            .as_str()
+                            .expect("scroll_id is of wrong type")
+                            .to_owned();
+
+                        // fetch the next batch; a scroll continuation advances
+                        // the cursor server-side, so only a 429 (rejected before
+                        // the cursor moves) is safe to retry — re-issuing after a
+                        // 5xx/transport loss could skip an already-served batch
+                        let next = with_retry_throttle(max_retries, {
+                            let client = client.clone();
+                            move || {
+                                let request = ScrollRequest::for_scroll_id(
+                                    scroll_id.clone(),
+                                    json!({
+                                        "scroll": "1m"
+                                    }),
+                                );
+                                read(client.request(request).send())
+                            }
+                        });
+
+                        // loop on the next batch
+                        Either::A(next.and_then(|value: Value| {
+                            Ok(Loop::Continue((counter, client, format, value)))
+                        }))
+                    },
+                )
             });
 
         // push the worker
@@ -150,16 +199,279 @@ pub fn run(args: &ArgMatches) -> Box<Future<Item = (), Error = Error>> {
     )
 }
 
-/// Attempts to parse a host/index pair out of the CLI arguments.
+/// Drives the export using a Point-in-Time and `search_after` paging.
+///
+/// A single PIT is opened for the target index and shared across every slice,
+/// so there is no per-worker scroll context to exhaust. Each worker pages
+/// through its slice with `search_after` against the last hit's `sort` array,
+/// and the PIT is released with `DELETE /_pit` whether the export succeeds or
+/// fails.
+fn run_pit(
+    args: &ArgMatches,
+    client: Arc<AsyncClient>,
+    index: String,
+    workers: usize,
+    counter: Arc<AtomicUsize>,
+    format: Format,
+    max_retries: usize,
+) -> Box<dyn Future<Item = (), Error = Error>> {
+    // collect the per-worker base queries up front so a malformed query fails
+    // before we bother opening a Point-in-Time we'd then have to close
+    let mut queries = Vec::with_capacity(workers);
+    for idx in 0..workers {
+        match construct_pit_query(args, idx, workers) {
+            Ok(query) => queries.push(query),
+            Err(e) => return Box::new(future::err(e)),
+        }
+    }
+
+    let client = client.clone();
+
+    // open the PIT, then fan out the workers, then always close the PIT
+    let execute = open_pit(&client, &index)
+        .and_then(move |pit_id| {
+            // run every slice against the shared PIT id
+            let mut tasks = Vec::with_capacity(queries.len());
+            for query in queries {
+                let task = pit_worker(
+                    client.clone(),
+                    pit_id.clone(),
+                    query,
+                    counter.clone(),
+                    format.clone(),
+                    max_retries,
+                );
+                tasks.push(task);
+            }
+
+            // join the slices, carrying the client/pit so we can close it
+            future::join_all(tasks)
+                .map_err(|e| format_err!("{}", e.to_string()))
+                .then(move |result| {
+                    // release the PIT regardless of the export outcome
+                    close_pit(&client, &pit_id).then(|_| result.map(|_| ()))
+                })
+        });
+
+    Box::new(execute)
+}
+
+/// Assembles a raw [`Endpoint`] for the ad-hoc paths elastic has no typed
+/// request builder for.
+///
+/// The scroll export rides the typed `SearchRequest`/`ScrollRequest` builders;
+/// the Point-in-Time calls hit bespoke paths (`/{index}/_pit`, `/_pit`, and the
+/// bare `/_search`), so we hand the client an `Endpoint` directly.
+fn raw_endpoint(method: Method, path: String, body: Vec<u8>) -> Endpoint<'static, Vec<u8>> {
+    Endpoint {
+        url: UrlPath::from(path),
+        method,
+        body: Some(body),
+    }
+}
+
+/// Opens a Point-in-Time against the target index, returning its id.
+fn open_pit(client: &Arc<AsyncClient>, index: &str) -> Box<dyn Future<Item = String, Error = Error>> {
+    let path = format!("/{}/_pit", index);
+    let request = raw_endpoint(Method::POST, path, Vec::new());
+
+    let open = client
+        .request(request)
+        .params_fluent(|p| p.url_param("keep_alive", "1m"))
+        .send()
+        .and_then(AsyncResponseBuilder::into_response)
+        .map_err(|e| format_err!("{}", e.to_string()))
+        .and_then(|value: Value| {
+            value
+                .get("id")
+                .and_then(Value::as_str)
+                .map(str::to_owned)
+                .ok_or_else(|| format_err!("PIT response missing id: {}", value))
+        });
+
+    Box::new(open)
+}
+
+/// Closes a Point-in-Time, freeing its resources on the cluster.
+fn close_pit(client: &Arc<AsyncClient>, pit_id: &str) -> Box<dyn Future<Item = (), Error = Error>> {
+    let body = json!({ "id": pit_id }).to_string().into_bytes();
+    let request = raw_endpoint(Method::DELETE, "/_pit".to_owned(), body);
+
+    let close = client
+        .request(request)
+        .send()
+        .and_then(AsyncResponseBuilder::into_response)
+        .map_err(|e| format_err!("{}", e.to_string()))
+        .map(|_: Value| ());
+
+    Box::new(close)
+}
+
+/// Runs a single slice's `search_after` paging loop against the shared PIT.
+fn pit_worker(
+    client: Arc<AsyncClient>,
+    pit_id: String,
+    query: Value,
+    counter: Arc<AtomicUsize>,
+    format: Format,
+    max_retries: usize,
+) -> Box<dyn Future<Item = (), Error = Error>> {
+    // the loop carries the `search_after` cursor between batches; `None`
+    // marks the very first request, which omits the cursor entirely
+    let state = (client, pit_id, query, counter, format, None::<Value>);
+
+    let paged = future::loop_fn(state, move |(client, pit_id, query, counter, format, after)| {
+        // assemble the request body for this page, injecting the PIT and,
+        // on every batch after the first, the `search_after` cursor
+        let mut body = query.clone();
+        {
+            let obj = body.as_object_mut().unwrap();
+            obj.insert("pit".to_owned(), json!({ "id": pit_id, "keep_alive": "1m" }));
+            if let Some(after) = &after {
+                obj.insert("search_after".to_owned(), after.clone());
+            }
+        }
+
+        // issue the page against the PIT, retrying transient failures; the
+        // search hits the bare `/_search` path because ES rejects naming an
+        // index alongside a `pit`, and `SearchRequest::for_index("")` would
+        // format `//_search` rather than `/_search`
+        with_retry(max_retries, {
+            let client = client.clone();
+            move || {
+                let body = body.to_string().into_bytes();
+                let request = raw_endpoint(Method::POST, "/_search".to_owned(), body);
+                read(client.request(request).send())
+            }
+        })
+            .and_then(move |mut value: Value| {
+                // fetch the hits back
+                let hits = value
+                    .pointer_mut("/hits/hits")
+                    .expect("unable to locate hits")
+                    .as_array_mut()
+                    .expect("hits are of wrong type");
+
+                // empty hits means this slice is drained
+                if hits.is_empty() {
+                    return Ok(Loop::Break(()));
+                }
+
+                // store hit length and the cursor for the next page
+                let len = hits.len();
+                let next = hits
+                    .last()
+                    .and_then(|hit| hit.get("sort").cloned())
+                    .ok_or_else(|| format_err!("hit missing sort for search_after"))?;
+
+                // serialize every hit via the shared output format
+                for hit in hits {
+                    let container = hit.as_object_mut().unwrap();
+                    container.remove("sort");
+                    container.remove("_score");
+                    format.render(hit)?;
+                }
+
+                // increment the counter and print the state to stderr
+                let cnt = counter.fetch_add(len, Ordering::Relaxed);
+                eprintln!("Fetched batch of {}, have now processed {}", len, cnt + len);
+
+                // loop on with the new cursor in hand
+                Ok(Loop::Continue((
+                    client,
+                    pit_id,
+                    query,
+                    counter,
+                    format,
+                    Some(next),
+                )))
+            })
+    });
+
+    Box::new(paged)
+}
+
+/// Constructs a slice's base query for the Point-in-Time paging loop.
+///
+/// Unlike [`construct_query`], the sort ends in the `_shard_doc` tiebreaker so
+/// that `search_after` has a total ordering to page against, and no `_doc`
+/// sort (which is scroll-only) is emitted.
+fn construct_pit_query(args: &ArgMatches, id: usize, max: usize) -> Result<Value, Error> {
+    // fetch the configured batch size, or default to 100
+    let size = value_t!(args, "size", usize).unwrap_or(100);
+
+    // fetch the query filter to use to limit matches (defaults to all docs)
+    let filter = args.value_of("query").unwrap_or("{\"match_all\":{}}");
+    let filter = serde_json::from_str::<Value>(filter)?;
+
+    // construct query with a tiebreaker sort for stable paging
+    let mut query = json!({
+        "query": filter,
+        "size": size,
+        "sort": [
+            { "_shard_doc": "asc" }
+        ]
+    });
+
+    // handle multiple workers by slicing against the PIT
+    if max > 1 {
+        query.as_object_mut().unwrap().insert(
+            "slice".to_owned(),
+            json!({
+                "id": id,
+                "max": max
+            }),
+        );
+    }
+
+    Ok(query)
+}
+
+/// Attempts to parse a set of seed nodes plus an index out of the CLI args.
 ///
-/// This logic is pretty vague; we don't actually test connection beyond
-/// looking to see if the provided scheme is HTTP(S). The index string
-/// returned will never be empty; if no index is provided, we'll use the
-/// ES "_all" alias to avoid having to deal with `Option` types for now.
-fn parse_cluster_info(args: &ArgMatches) -> Result<(String, String), Error> {
-    // fetch the source from the arguments, should always be possible
+/// The `source` argument may be a single URL or a comma-separated list of
+/// them, and any number of additional nodes may be supplied via the
+/// repeatable `--node` flag. Every node is validated to be HTTP(S); the index
+/// and inline credentials are taken from the first node (they are assumed to
+/// be uniform across the cluster). The index string returned will never be
+/// empty; if no index is provided, we'll use the ES "_all" alias to avoid
+/// having to deal with `Option` types for now.
+pub(crate) fn parse_cluster_info(
+    args: &ArgMatches,
+) -> Result<(Vec<String>, String, Credentials), Error> {
+    // gather every seed node: the (possibly comma-separated) source plus any
+    // repeatable `--node` flags, preserving order for round-robin stability
     let source = args.value_of("source").expect("guaranteed by CLI");
+    let seeds = source
+        .split(',')
+        .chain(args.values_of("node").into_iter().flatten())
+        .map(str::trim)
+        .filter(|s| !s.is_empty());
+
+    let mut hosts = Vec::new();
+    let mut index = None;
+    let mut creds = None;
+
+    for (pos, seed) in seeds.enumerate() {
+        // parse and normalise this node into a bare host address
+        let (host, node_index, node_creds) = parse_node(seed)?;
+        hosts.push(host);
+
+        // the index and credentials come from the first node only
+        if pos == 0 {
+            index = Some(node_index);
+            creds = node_creds;
+        }
+    }
+
+    // at least one node must have survived the filtering above
+    let index = index.ok_or_else(|| format_err!("no cluster nodes provided"))?;
+
+    Ok((hosts, index, creds))
+}
 
+/// Parses a single node URL into its bare host, index, and inline credentials.
+fn parse_node(source: &str) -> Result<(String, String, Credentials), Error> {
     // attempt to parse the resource
     let mut url = Url::parse(source)?;
 
@@ -172,20 +484,149 @@ fn parse_cluster_info(args: &ArgMatches) -> Result<(String, String), Error> {
     let index = url.path().trim_start_matches('/');
 
     // set default index
-    if index.is_empty() {
-        "_all"
-    } else {
-        index
-    };
+    let index = if index.is_empty() { "_all" } else { index };
 
     // take ownership to enable mut url
     let index = index.to_owned();
 
+    // lift any inline `user:pass@` userinfo out of the URL before we trim it
+    let creds = match (url.username(), url.password()) {
+        ("", _) => None,
+        (user, Some(pass)) => Some((user.to_owned(), pass.to_owned())),
+        (user, None) => Some((user.to_owned(), String::new())),
+    };
+
+    // strip the userinfo so it never leaks into the node address
+    url.set_username("").ok();
+    url.set_password(None).ok();
+
     // trim the path
     url.set_path("");
 
     // assume we have a cluster now, so pass it back
-    Ok((url.as_str().trim_end_matches('/').to_owned(), index))
+    Ok((url.as_str().trim_end_matches('/').to_owned(), index, creds))
+}
+
+/// Builds an async Elasticsearch client over the given seed nodes, honouring
+/// the security-related CLI flags.
+///
+/// All seed nodes are registered as a multi-node pool. elastic cycles through
+/// the pool round-robin, advancing one node per request, so successive requests
+/// from the workers land on different nodes instead of hammering one
+/// coordinating node. (This is request-level rotation, not a per-worker-index
+/// binding, and the static pool does not fail a request over to another node on
+/// a connection error.) When `--sniff` is set the live node list is discovered
+/// from the first seed at startup via `GET /_nodes/http` and the published
+/// addresses are used in place of the static list.
+///
+/// Basic auth is resolved from `--username`/`--password` first, falling back
+/// to any `user:pass@` userinfo stripped from the source URL. An explicit
+/// `--api-key` or `--bearer` token is sent as an `Authorization` header with
+/// the `ApiKey` or `Bearer` scheme prefix and takes precedence over basic auth. For `https://` sources the
+/// TLS backend is selected at compile time via the `native-tls`/`rustls`
+/// features, and a custom `--cacert` or `--insecure` flag tunes verification.
+pub(crate) fn build_client(
+    args: &ArgMatches,
+    hosts: Vec<String>,
+    creds: Credentials,
+) -> Result<AsyncClient, Error> {
+    // resolve the Authorization header, if any security flag is in play
+    let auth = resolve_auth(args, creds)?;
+
+    // the first seed drives the TLS config and any node sniffing
+    let first = hosts
+        .first()
+        .cloned()
+        .ok_or_else(|| format_err!("no cluster nodes provided"))?;
+
+    // configure the TLS-capable HTTP sender used to reach the nodes
+    let http = build_http_client(args, &first)?;
+
+    // assemble the client over the configured sender and node pool; sniffing
+    // replaces the static seeds with the cluster's published http addresses
+    let mut builder = AsyncClientBuilder::new().http_client(http);
+    builder = if args.is_present("sniff") {
+        builder.sniff_nodes(first)
+    } else {
+        builder.static_nodes(hosts)
+    };
+
+    // attach the Authorization header to every outgoing request
+    if let Some(value) = auth {
+        builder = builder.params_fluent(move |p| p.header(AUTHORIZATION, value.clone()));
+    }
+
+    // build, normalising the backend error into our `failure` chain
+    builder.build().map_err(|e| format_err!("{}", e.to_string()))
+}
+
+/// Resolves the `Authorization` header value from the security flags.
+///
+/// A bearer/API-key token wins outright; otherwise basic auth is assembled
+/// from the explicit flags or the inline userinfo. `None` means the cluster
+/// is unsecured and no header should be sent.
+fn resolve_auth(args: &ArgMatches, creds: Credentials) -> Result<Option<HeaderValue>, Error> {
+    // an explicit token is sent under its auth scheme and short-circuits basic auth
+    if let Some(token) = args.value_of("bearer") {
+        let value = HeaderValue::from_str(&format!("Bearer {}", token))?;
+        return Ok(Some(value));
+    }
+    if let Some(key) = args.value_of("api-key") {
+        let value = HeaderValue::from_str(&format!("ApiKey {}", key))?;
+        return Ok(Some(value));
+    }
+
+    // flags override inline userinfo so the URL can be left anonymous
+    let user = args.value_of("username").map(str::to_owned);
+    let (user, pass) = match (user, creds) {
+        (Some(user), _) => (user, args.value_of("password").unwrap_or("").to_owned()),
+        (None, Some((user, pass))) => (user, pass),
+        (None, None) => return Ok(None),
+    };
+
+    // encode `user:pass` as standard HTTP Basic credentials
+    let raw = base64::encode(&format!("{}:{}", user, pass));
+    let value = HeaderValue::from_str(&format!("Basic {}", raw))?;
+    Ok(Some(value))
+}
+
+/// Builds the underlying HTTP client, applying any TLS configuration.
+///
+/// Plain `http://` nodes need nothing beyond the default client. For secured
+/// nodes we honour `--cacert` (trust an additional root) and `--insecure`
+/// (skip verification for self-signed dev clusters), using whichever TLS
+/// backend was compiled in.
+fn build_http_client(args: &ArgMatches, host: &str) -> Result<AsyncHttpClient, Error> {
+    // unsecured clusters don't need any certificate plumbing
+    if !host.starts_with("https") {
+        return Ok(AsyncHttpClient::new());
+    }
+
+    let mut builder = AsyncHttpClient::builder();
+
+    // trust an additional CA certificate when one is supplied
+    if let Some(path) = args.value_of("cacert") {
+        let pem = std::fs::read(path)?;
+        let cert = reqwest::Certificate::from_pem(&pem)?;
+        builder = builder.add_root_certificate(cert);
+    }
+
+    // allow self-signed dev clusters to be reached with verification off
+    if args.is_present("insecure") {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+
+    // pick the TLS backend that was selected at compile time
+    #[cfg(feature = "rustls")]
+    {
+        builder = builder.use_rustls_tls();
+    }
+    #[cfg(all(feature = "native-tls", not(feature = "rustls")))]
+    {
+        builder = builder.use_default_tls();
+    }
+
+    builder.build().map_err(|e| format_err!("{}", e.to_string()))
 }
 
 /// Constructs a query instance based on the worker count and identifier.
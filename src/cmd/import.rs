@@ -0,0 +1,239 @@
+//! Import command module for Limber.
+//!
+//! This module is the inverse of `export`; it consumes the newline-delimited
+//! JSON stream produced on `stdout` by the export command and replays it into
+//! a target cluster/index via the Elasticsearch `_bulk` API.
+//!
+//! Because `export` writes one hit object per line, the two commands compose
+//! directly: `limber export $src | limber import $dst` streams one cluster
+//! into another without ever buffering the whole index on disk.
+use clap::{value_t, ArgMatches};
+use elastic::endpoints::BulkRequest;
+use failure::{format_err, Error};
+use futures::future::{self, Either, Loop};
+use futures::prelude::*;
+use serde_json::{json, Value};
+
+use std::io::{self, BufRead, BufReader};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use super::export::{build_client, parse_cluster_info};
+use super::retry::{read, with_retry};
+
+/// Constructs a `Future` to execute the `import` command.
+///
+/// This future should be spawned on a Runtime to carry out the importing
+/// process. It reads hit objects off `stdin`, batches them into `_bulk`
+/// request bodies, and replays each batch against the target cluster in
+/// turn, tracking the number of indexed documents on `stderr`.
+pub fn run(args: &ArgMatches) -> Box<dyn Future<Item = (), Error = Error>> {
+    // parse arguments into a host/index pairing for later
+    let (hosts, index, creds) = match parse_cluster_info(args) {
+        Ok(info) => info,
+        Err(e) => {
+            let err = future::err(e);
+            return Box::new(err);
+        }
+    };
+
+    // construct a single client instance to feed the bulk requests
+    let client = match build_client(args, hosts, creds) {
+        Ok(client) => Arc::new(client),
+        Err(e) => {
+            let err = future::err(e);
+            return Box::new(err);
+        }
+    };
+
+    // wrap stdin in a lazy batcher; lines are read and assembled into `_bulk`
+    // bodies one batch at a time, never holding the whole stream in memory
+    let batcher = Batcher::new(args, index);
+
+    // fetch the retry budget applied to each bulk request
+    let max_retries = value_t!(args, "max-retries", usize).unwrap_or(5);
+
+    // create counter to track documents indexed
+    let counter = Arc::new(AtomicUsize::new(0));
+
+    // replay each batch in turn, looping until the stream is drained
+    let execute = future::loop_fn(
+        (counter, client, batcher),
+        move |(counter, client, mut batcher)| {
+            // read (and build) the next batch straight off the line stream
+            let (body, len) = match batcher.next_batch() {
+                Ok(Some(batch)) => batch,
+                Ok(None) => {
+                    let ctx = (counter, client, batcher);
+                    let brk = Loop::Break(ctx);
+                    let okr = future::ok(brk);
+                    return Either::B(okr);
+                }
+                Err(e) => return Either::B(future::err(e)),
+            };
+
+            // fire the batch, retrying transient failures, then inspect the
+            // response for per-item failures
+            let indexed = with_retry(max_retries, {
+                let client = client.clone();
+                move || {
+                    let request = BulkRequest::new(body.clone());
+                    read(client.request(request).send())
+                }
+            });
+
+            Either::A(
+                indexed
+                    .and_then(move |value: Value| {
+                        // surface any per-item errors rather than dropping them
+                        check_bulk_errors(&value)?;
+
+                        // increment the counter and print the state to stderr
+                        let cnt = counter.fetch_add(len, Ordering::Relaxed);
+                        eprintln!("Indexed batch of {}, have now processed {}", len, cnt + len);
+
+                        // loop on to the next batch
+                        Ok(Loop::Continue((counter, client, batcher)))
+                    }),
+            )
+        },
+    );
+
+    // map away the loop context once the stream is exhausted
+    Box::new(execute.map(|_| ()))
+}
+
+/// A lazy reader that turns the `stdin` line stream into `_bulk` request bodies
+/// one batch at a time.
+///
+/// Each line is expected to be a hit object carrying `_index`, `_id` and
+/// `_source`, exactly as emitted by `export`. [`Batcher::next_batch`] pulls
+/// lines until either the `--size` document count or the byte threshold is
+/// reached and returns just that batch, so a `limber export | limber import`
+/// pipe never buffers the whole index in memory.
+///
+/// A malformed line halts the import; we would rather fail loudly than index
+/// a partial stream and leave the target in an inconsistent state.
+struct Batcher {
+    lines: io::Lines<BufReader<io::Stdin>>,
+    index: String,
+    size: usize,
+    bytes: usize,
+    num: usize,
+}
+
+impl Batcher {
+    /// Builds a batcher over `stdin`, reading the `--size`/`--bytes` thresholds.
+    fn new(args: &ArgMatches, index: String) -> Batcher {
+        Batcher {
+            lines: BufReader::new(io::stdin()).lines(),
+            index,
+            // fetch the configured batch size, or default to 100
+            size: value_t!(args, "size", usize).unwrap_or(100),
+            // fetch the byte threshold used as a secondary flush trigger (~5MiB)
+            bytes: value_t!(args, "bytes", usize).unwrap_or(5 * 1024 * 1024),
+            num: 0,
+        }
+    }
+
+    /// Assembles the next `_bulk` body, or `None` once the stream is drained.
+    ///
+    /// Lines are accumulated until either threshold trips; a trailing partial
+    /// batch is returned before the stream ends.
+    fn next_batch(&mut self) -> Result<Option<(String, usize)>, Error> {
+        // state for the batch currently being assembled
+        let mut body = String::new();
+        let mut count = 0;
+
+        // walk the stream one line at a time until a threshold trips
+        while let Some(line) = self.lines.next() {
+            // propagate any IO failure on the underlying stream
+            let line = line?;
+            self.num += 1;
+
+            // skip blank lines so trailing newlines don't break parsing
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            // parse the hit, attributing the failure to the offending line
+            let hit = serde_json::from_str::<Value>(&line)
+                .map_err(|e| format_err!("invalid JSON on line {}: {}", self.num, e))?;
+
+            // append the action/metadata + source pair to the batch body
+            append_action(&mut body, &hit, &self.index)?;
+            count += 1;
+
+            // flush once either threshold trips
+            if count >= self.size || body.len() >= self.bytes {
+                return Ok(Some((body, count)));
+            }
+        }
+
+        // flush any trailing partial batch, else signal the stream is drained
+        if count > 0 {
+            Ok(Some((body, count)))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Appends a single document to a `_bulk` request body.
+///
+/// The Elasticsearch bulk format is a pair of newline-delimited JSON objects:
+/// an `index` action carrying the destination `_index`/`_id`, immediately
+/// followed by the `_source` document itself.
+fn append_action(body: &mut String, hit: &Value, index: &str) -> Result<(), Error> {
+    // the destination index falls back to the target when the hit omits it
+    let dst = hit.get("_index").and_then(Value::as_str).unwrap_or(index);
+
+    // the document body is required; an envelope without it is meaningless
+    let source = hit
+        .get("_source")
+        .ok_or_else(|| format_err!("hit is missing _source: {}", hit))?;
+
+    // the id is optional; Elasticsearch will allocate one when absent
+    let mut meta = json!({ "_index": dst });
+    if let Some(id) = hit.get("_id") {
+        meta.as_object_mut().unwrap().insert("_id".to_owned(), id.clone());
+    }
+
+    // emit the alternating action/metadata then source lines
+    body.push_str(&json!({ "index": meta }).to_string());
+    body.push('\n');
+    body.push_str(&source.to_string());
+    body.push('\n');
+
+    Ok(())
+}
+
+/// Inspects a `_bulk` response body for per-item failures.
+///
+/// The bulk API answers `200 OK` even when individual items fail, flagging the
+/// batch with a top-level `errors: true`. We walk the `items` array and report
+/// the first failing action so the caller isn't left believing a partial batch
+/// succeeded.
+fn check_bulk_errors(value: &Value) -> Result<(), Error> {
+    // the happy path leaves `errors` false (or absent on odd responses)
+    if !value.get("errors").and_then(Value::as_bool).unwrap_or(false) {
+        return Ok(());
+    }
+
+    // locate the per-item results to pick out the offending entries
+    let items = value
+        .get("items")
+        .and_then(Value::as_array)
+        .ok_or_else(|| format_err!("bulk response flagged errors but carried no items"))?;
+
+    // surface the first item that reported an `error` object
+    for item in items {
+        if let Some(action) = item.as_object().and_then(|o| o.values().next()) {
+            if let Some(error) = action.get("error") {
+                return Err(format_err!("bulk item failed: {}", error));
+            }
+        }
+    }
+
+    Ok(())
+}
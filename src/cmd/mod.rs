@@ -0,0 +1,9 @@
+//! Command implementations for Limber.
+//!
+//! Each public subcommand lives in its own module and exposes a `run` entry
+//! point returning a spawnable `Future`; the remaining modules back those
+//! commands with shared serialization and retry helpers.
+pub mod export;
+pub mod format;
+pub mod import;
+pub mod retry;
@@ -0,0 +1,206 @@
+//! Retry helpers for transient scroll/search/bulk failures.
+//!
+//! A long-running export issues thousands of requests against the cluster; a
+//! single dropped connection, node restart, or `429` rejection should not
+//! abort the whole job and lose hours of progress. [`with_retry`] re-issues a
+//! request on retryable failures with exponential backoff plus jitter, only
+//! giving up once the attempt budget is exhausted. Non-retryable failures
+//! (4xx other than 429, malformed queries) fail fast.
+use elastic::http::receiver::AsyncResponseBuilder;
+use elastic::http::StatusCode;
+use elastic::Error as ElasticError;
+use failure::{format_err, Error, Fail};
+use futures::future::{self, Either, Loop};
+use futures::prelude::*;
+use rand::Rng;
+use serde_json::Value;
+use tokio_timer::Delay;
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// The base backoff, doubled on each successive attempt.
+const BASE_BACKOFF: Duration = Duration::from_millis(500);
+
+/// The ceiling the doubling backoff is clamped to.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Retries a request-issuing closure with exponential backoff and jitter.
+///
+/// `op` is invoked afresh on each attempt so the same scroll/search/bulk
+/// request is re-issued, not a stale future. It fails fast on non-retryable
+/// errors and after `max_retries` total attempts; otherwise it sleeps for a
+/// jittered, exponentially increasing delay before trying again.
+pub(crate) fn with_retry<F, R>(
+    max_retries: usize,
+    op: F,
+) -> Box<dyn Future<Item = Value, Error = Error>>
+where
+    F: Fn() -> R + 'static,
+    R: Future<Item = Value, Error = Error> + 'static,
+{
+    retry_with(max_retries, is_retryable, op)
+}
+
+/// Retries only on `429` throttling, for requests that are *not* idempotent.
+///
+/// A scroll continuation advances the cursor server-side, so a 5xx or dropped
+/// connection may mean the batch was already processed before the response was
+/// lost — re-issuing it would silently skip those documents. A `429`, by
+/// contrast, is rejected before the scroll advances, so it is always safe to
+/// retry. Idempotent requests (the opening search, PIT `search_after`) should
+/// use [`with_retry`], which retries the full transient set.
+pub(crate) fn with_retry_throttle<F, R>(
+    max_retries: usize,
+    op: F,
+) -> Box<dyn Future<Item = Value, Error = Error>>
+where
+    F: Fn() -> R + 'static,
+    R: Future<Item = Value, Error = Error> + 'static,
+{
+    retry_with(max_retries, is_throttle, op)
+}
+
+/// Shared retry loop, parameterised by which failures count as retryable.
+fn retry_with<F, R>(
+    max_retries: usize,
+    retryable: fn(&Error) -> bool,
+    op: F,
+) -> Box<dyn Future<Item = Value, Error = Error>>
+where
+    F: Fn() -> R + 'static,
+    R: Future<Item = Value, Error = Error> + 'static,
+{
+    let paged = future::loop_fn(0usize, move |attempt| {
+        op().then(move |result| match result {
+            // success breaks the loop with the response body
+            Ok(value) => Either::A(future::ok(Loop::Break(value))),
+
+            // a failure is either fatal or scheduled for another attempt
+            Err(error) => {
+                if attempt + 1 >= max_retries || !retryable(&error) {
+                    return Either::A(future::err(error));
+                }
+
+                // back off before re-issuing the request
+                let when = Instant::now() + backoff(attempt);
+                let delay = Delay::new(when)
+                    .map_err(|e| format_err!("{}", e))
+                    .map(move |_| Loop::Continue(attempt + 1));
+
+                Either::B(delay)
+            }
+        })
+    });
+
+    Box::new(paged)
+}
+
+/// Computes the jittered backoff for a zero-based attempt number.
+///
+/// The delay is `BASE_BACKOFF * 2^attempt`, clamped to `MAX_BACKOFF`, with up
+/// to an additional 100% of full jitter to spread retries from many workers.
+fn backoff(attempt: usize) -> Duration {
+    // double the base per attempt, saturating rather than overflowing
+    let scaled = BASE_BACKOFF
+        .checked_mul(1u32 << attempt.min(16))
+        .unwrap_or(MAX_BACKOFF)
+        .min(MAX_BACKOFF);
+
+    // add full jitter in `[0, scaled]` to decorrelate concurrent workers
+    let jitter = rand::thread_rng().gen_range(0, scaled.as_millis() as u64 + 1);
+    scaled + Duration::from_millis(jitter)
+}
+
+/// Issues a request and decodes its body, tagging any failure as retryable or
+/// not based on the response's HTTP status (or the transport layer).
+///
+/// The retry decision is made here, while we still have the typed response in
+/// hand, rather than downstream against an error's `Display` text. `elastic`'s
+/// `into_response` surfaces server errors as an `ApiError` whose `Display`
+/// carries the ES error *type* (e.g. `es_rejected_execution_exception`) and not
+/// the HTTP status, so a numeric substring check would essentially never fire.
+/// We instead read the status off the `AsyncResponseBuilder` before consuming
+/// it, and treat send-time failures (dropped connections, timeouts) — which
+/// never produce a response at all — as transient by definition.
+pub(crate) fn read<S>(pending: S) -> Box<dyn Future<Item = Value, Error = Error>>
+where
+    S: Future<Item = AsyncResponseBuilder, Error = ElasticError> + 'static,
+{
+    let read = pending
+        // a failure before any response is a transport error: always transient
+        .map_err(|e| Error::from(Transient::transport(&e)))
+        .and_then(|response| {
+            // capture the status before `into_response` consumes the builder
+            let status = response.status();
+            response
+                .into_response::<Value>()
+                .map_err(move |e| Error::from(Transient::from_status(status, &e)))
+        });
+
+    Box::new(read)
+}
+
+/// Classifies an error as retryable.
+///
+/// Retryability is decided at the call site by [`read`], which records it on a
+/// [`Transient`] carried through the `failure` chain. A [`Transient`] tagged
+/// non-retryable (a 4xx other than 429, such as a malformed query) and any
+/// error that is not a [`Transient`] at all fail fast.
+fn is_retryable(error: &Error) -> bool {
+    error
+        .downcast_ref::<Transient>()
+        .map(|t| t.retryable)
+        .unwrap_or(false)
+}
+
+/// Classifies an error as `429` throttling, the only failure safe to retry on
+/// a non-idempotent request. See [`with_retry_throttle`].
+fn is_throttle(error: &Error) -> bool {
+    error
+        .downcast_ref::<Transient>()
+        .map(|t| t.throttle)
+        .unwrap_or(false)
+}
+
+/// A request failure tagged with whether it is worth retrying.
+///
+/// We treat connection drops/timeouts, 5xx responses, and `429` throttling as
+/// transient; 4xx client errors (malformed queries, missing indices) are not.
+#[derive(Debug)]
+struct Transient {
+    retryable: bool,
+    /// Whether the failure is a `429` — the subset safe to retry even when the
+    /// request is not idempotent, since the server rejects it before acting.
+    throttle: bool,
+    message: String,
+}
+
+impl Transient {
+    /// Builds a transport failure (no response was received), always transient.
+    fn transport(error: &ElasticError) -> Transient {
+        Transient {
+            retryable: true,
+            throttle: false,
+            message: error.to_string(),
+        }
+    }
+
+    /// Builds a response failure, transient only for 429 and 5xx statuses.
+    fn from_status(status: StatusCode, error: &ElasticError) -> Transient {
+        let throttle = status == StatusCode::TOO_MANY_REQUESTS;
+        Transient {
+            retryable: throttle || status.is_server_error(),
+            throttle,
+            message: error.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Transient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Fail for Transient {}
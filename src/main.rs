@@ -0,0 +1,152 @@
+//! Limber: stream Elasticsearch indices in and out of a cluster.
+//!
+//! Limber exposes two complementary subcommands. `export` scrolls (or pages,
+//! with `--pit`) an index to `stdout` in the selected `--format`, and `import`
+//! replays such a stream into a target cluster via the `_bulk` API. Because the
+//! two compose over a pipe, `limber export $src | limber import $dst` copies one
+//! cluster into another without buffering the whole index.
+mod cmd;
+
+use clap::{App, AppSettings, Arg, SubCommand};
+
+fn main() {
+    let matches = build_cli().get_matches();
+
+    // resolve the chosen subcommand into its export/import future
+    let (name, args) = matches.subcommand();
+    let args = args.expect("subcommand arguments guaranteed by clap");
+    let task = match name {
+        "export" => cmd::export::run(args),
+        "import" => cmd::import::run(args),
+        _ => unreachable!("subcommand guaranteed by clap"),
+    };
+
+    // drive the task to completion, surfacing any failure on stderr; a
+    // single-threaded runtime keeps the elastic futures (which are not `Send`)
+    // off a worker threadpool that would require them to be
+    let mut runtime =
+        tokio::runtime::current_thread::Runtime::new().expect("unable to start runtime");
+    if let Err(e) = runtime.block_on(task) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Assembles the `limber` command-line interface.
+fn build_cli() -> App<'static, 'static> {
+    App::new("limber")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Stream Elasticsearch indices in and out of a cluster")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("export")
+                .about("Export an index to stdout")
+                .args(&connection_args())
+                .arg(
+                    Arg::with_name("workers")
+                        .long("workers")
+                        .takes_value(true)
+                        .help("Number of concurrent worker slices [default: CPU count]"),
+                )
+                .arg(
+                    Arg::with_name("size")
+                        .long("size")
+                        .takes_value(true)
+                        .help("Documents fetched per batch [default: 100]"),
+                )
+                .arg(
+                    Arg::with_name("query")
+                        .long("query")
+                        .takes_value(true)
+                        .help("Query DSL filter applied to the export [default: match_all]"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["hit", "source", "ndjson-bulk", "csv"])
+                        .help("Output serialization [default: hit]"),
+                )
+                .arg(
+                    Arg::with_name("fields")
+                        .long("fields")
+                        .takes_value(true)
+                        .multiple(true)
+                        .use_delimiter(true)
+                        .required_if("format", "csv")
+                        .help("CSV column set, pinning the header (required for --format csv)"),
+                )
+                .arg(
+                    Arg::with_name("pit")
+                        .long("pit")
+                        .help("Page with a Point-in-Time + search_after instead of scroll"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("import")
+                .about("Import a stdout stream into an index")
+                .args(&connection_args())
+                .arg(
+                    Arg::with_name("size")
+                        .long("size")
+                        .takes_value(true)
+                        .help("Documents per bulk batch [default: 100]"),
+                )
+                .arg(
+                    Arg::with_name("bytes")
+                        .long("bytes")
+                        .takes_value(true)
+                        .help("Byte threshold flushing a bulk batch [default: 5MiB]"),
+                ),
+        )
+}
+
+/// The connection-level arguments shared by every subcommand.
+///
+/// These cover node discovery (`source`/`--node`/`--sniff`), authentication
+/// (`--username`/`--password`, `--api-key`/`--bearer`), TLS trust
+/// (`--cacert`/`--insecure`), and the per-request retry budget.
+fn connection_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("source")
+            .required(true)
+            .help("Cluster URL, optionally with an index path and inline user:pass@ credentials"),
+        Arg::with_name("node")
+            .long("node")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help("Additional seed node URL (repeatable)"),
+        Arg::with_name("sniff")
+            .long("sniff")
+            .help("Discover the live node list from the first seed at startup"),
+        Arg::with_name("username")
+            .long("username")
+            .takes_value(true)
+            .help("HTTP Basic auth username"),
+        Arg::with_name("password")
+            .long("password")
+            .takes_value(true)
+            .help("HTTP Basic auth password"),
+        Arg::with_name("api-key")
+            .long("api-key")
+            .takes_value(true)
+            .conflicts_with("bearer")
+            .help("Elasticsearch API key, sent as an ApiKey Authorization header"),
+        Arg::with_name("bearer")
+            .long("bearer")
+            .takes_value(true)
+            .help("Bearer token, sent as a Bearer Authorization header"),
+        Arg::with_name("cacert")
+            .long("cacert")
+            .takes_value(true)
+            .help("Path to an additional CA certificate to trust (PEM)"),
+        Arg::with_name("insecure")
+            .long("insecure")
+            .help("Skip TLS certificate verification (self-signed dev clusters)"),
+        Arg::with_name("max-retries")
+            .long("max-retries")
+            .takes_value(true)
+            .help("Attempts per transient request failure [default: 5]"),
+    ]
+}